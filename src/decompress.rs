@@ -0,0 +1,239 @@
+// Real CDPR saves are a sequence of independently-compressed chunks
+// preceded by a directory of offsets/sizes, not one flat buffer. This
+// inflates each chunk and reassembles the buffer the tree reader expects.
+
+use byteorder::{ReadBytesExt, LE};
+use log::{trace, warn};
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+const CHUNK_MAGIC: &[u8; 4] = b"CSAV";
+
+// Each directory entry is a fixed 16 bytes (codec id, offset, compressed
+// size, decompressed size), so `chunk_count` can never legitimately
+// exceed what's left in the buffer; reject it before `Vec::with_capacity`
+// the same way `decompressed_size` is rejected below.
+const CHUNK_ENTRY_BYTES: usize = 16;
+
+// A crafted chunk directory can declare an arbitrary `decompressed_size`;
+// without a sanity check that value hits `Vec::with_capacity` before the
+// inflate (or any cross-check) even runs, so a single bogus entry can
+// force a multi-GB allocation attempt. Reject sizes past a hard cap or an
+// implausible expansion ratio before allocating anything.
+const MAX_CHUNK_DECOMPRESSED_BYTES: usize = 256 * 1024 * 1024;
+const MAX_EXPANSION_RATIO: usize = 1024;
+
+// Codec ids from the chunk directory. Only Zlib is implemented; Lz4/Zstd
+// are registered so they can be filled in without reshaping the directory
+// reader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Codec {
+    Zlib,
+    Lz4,
+    Zstd,
+}
+
+impl Codec {
+    fn from_id(id: u32) -> Option<Self> {
+        match id {
+            0 => Some(Codec::Zlib),
+            1 => Some(Codec::Lz4),
+            2 => Some(Codec::Zstd),
+            _ => None,
+        }
+    }
+
+    fn inflate(&self, compressed: &[u8], decompressed_size: usize) -> Option<Vec<u8>> {
+        if decompressed_size > MAX_CHUNK_DECOMPRESSED_BYTES {
+            warn!(
+                "chunk declares {} decompressed bytes, over the {} cap",
+                decompressed_size, MAX_CHUNK_DECOMPRESSED_BYTES
+            );
+            return None;
+        }
+        if decompressed_size > compressed.len().max(1) * MAX_EXPANSION_RATIO {
+            warn!(
+                "chunk claims an implausible expansion from {} to {} bytes",
+                compressed.len(), decompressed_size
+            );
+            return None;
+        }
+        match self {
+            Codec::Zlib => {
+                let mut out = Vec::with_capacity(decompressed_size);
+                let mut decoder = flate2::read::DeflateDecoder::new(compressed);
+                decoder.read_to_end(&mut out).ok()?;
+                Some(out)
+            }
+            Codec::Lz4 | Codec::Zstd => {
+                warn!("{:?} chunk codec not yet implemented", self);
+                None
+            }
+        }
+    }
+}
+
+struct ChunkEntry {
+    codec: Codec,
+    compressed_offset: u32,
+    compressed_size: u32,
+    decompressed_size: u32,
+}
+
+// Returns `raw` unchanged when there's no chunk-table magic at its head.
+// Returns `None` if the directory is malformed, a codec isn't supported,
+// or an inflated chunk doesn't match its declared size.
+pub fn decompress_payload(raw: &[u8]) -> Option<Vec<u8>> {
+    if raw.len() < 4 || &raw[0..4] != CHUNK_MAGIC {
+        return Some(raw.to_vec());
+    }
+
+    let mut input = Cursor::new(raw);
+    input.seek(SeekFrom::Start(4)).ok()?;
+    let chunk_count = input.read_u32::<LE>().ok()?;
+    trace!("chunk count: {}", chunk_count);
+
+    let remaining = raw.len().saturating_sub(input.position() as usize);
+    if chunk_count as usize > remaining / CHUNK_ENTRY_BYTES {
+        warn!(
+            "chunk count {} can't fit in the {} bytes left in the payload",
+            chunk_count, remaining
+        );
+        return None;
+    }
+
+    let mut entries = Vec::with_capacity(chunk_count as usize);
+    for _ in 0..chunk_count {
+        let codec_id = input.read_u32::<LE>().ok()?;
+        let codec = Codec::from_id(codec_id)?;
+        let compressed_offset = input.read_u32::<LE>().ok()?;
+        let compressed_size = input.read_u32::<LE>().ok()?;
+        let decompressed_size = input.read_u32::<LE>().ok()?;
+        trace!(
+            "chunk: codec={:?} offset={} compressed={} decompressed={}",
+            codec, compressed_offset, compressed_size, decompressed_size
+        );
+        entries.push(ChunkEntry {
+            codec,
+            compressed_offset,
+            compressed_size,
+            decompressed_size,
+        });
+    }
+
+    let mut out = Vec::new();
+    for entry in &entries {
+        let start = entry.compressed_offset as usize;
+        // The final chunk is commonly shorter than the others; clamp the
+        // end to the buffer length rather than requiring an exact fit.
+        // Both operands come straight from the chunk directory, so add as
+        // u64 first -- two offsets near u32::MAX would overflow a usize
+        // add on wasm32, where usize is 32 bits.
+        let end = (start as u64 + entry.compressed_size as u64).min(raw.len() as u64) as usize;
+        let compressed = raw.get(start..end)?;
+        let inflated = entry.codec.inflate(compressed, entry.decompressed_size as usize)?;
+        if inflated.len() != entry.decompressed_size as usize {
+            warn!(
+                "chunk at offset {} inflated to {} bytes, expected {}",
+                start, inflated.len(), entry.decompressed_size
+            );
+            return None;
+        }
+        out.extend_from_slice(&inflated);
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use byteorder::WriteBytesExt;
+    use std::io::Write;
+
+    fn deflate(data: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    // Builds a `CSAV` payload from (plaintext, declared_decompressed_size)
+    // pairs, each becoming one zlib-coded chunk.
+    fn build_payload(chunks: &[(&[u8], u32)]) -> Vec<u8> {
+        let mut directory = Vec::new();
+        let mut body = Vec::new();
+        let header_len = 4 + 4 + chunks.len() as u32 * 16;
+        let mut offset = header_len;
+        for (plain, declared_size) in chunks {
+            let compressed = deflate(plain);
+            directory.write_u32::<LE>(0).unwrap();
+            directory.write_u32::<LE>(offset).unwrap();
+            directory.write_u32::<LE>(compressed.len() as u32).unwrap();
+            directory.write_u32::<LE>(*declared_size).unwrap();
+            offset += compressed.len() as u32;
+            body.extend_from_slice(&compressed);
+        }
+
+        let mut out = Vec::new();
+        out.extend_from_slice(CHUNK_MAGIC);
+        out.write_u32::<LE>(chunks.len() as u32).unwrap();
+        out.extend_from_slice(&directory);
+        out.extend_from_slice(&body);
+        out
+    }
+
+    #[test]
+    fn passes_through_a_payload_with_no_chunk_magic() {
+        let raw = b"not a chunked payload".to_vec();
+        assert_eq!(decompress_payload(&raw), Some(raw));
+    }
+
+    #[test]
+    fn reassembles_chunks_including_a_short_final_chunk() {
+        let first = b"hello hello hello ".to_vec();
+        let last = b"!".to_vec();
+        let payload = build_payload(&[(&first, first.len() as u32), (&last, last.len() as u32)]);
+
+        let mut expected = first.clone();
+        expected.extend_from_slice(&last);
+        assert_eq!(decompress_payload(&payload), Some(expected));
+    }
+
+    #[test]
+    fn rejects_a_declared_size_that_does_not_match_the_inflated_output() {
+        let plain = b"mismatched declared size".to_vec();
+        let payload = build_payload(&[(&plain, plain.len() as u32 + 1)]);
+        assert_eq!(decompress_payload(&payload), None);
+    }
+
+    #[test]
+    fn rejects_an_implausible_decompressed_size_before_allocating() {
+        let plain = b"tiny".to_vec();
+        // A few bytes of compressed input claiming a multi-GB expansion
+        // should be rejected by the ratio/cap check, not attempted.
+        let payload = build_payload(&[(&plain, 0xFFFF_FFFF)]);
+        assert_eq!(decompress_payload(&payload), None);
+    }
+
+    #[test]
+    fn rejects_a_chunk_count_that_cannot_fit_in_the_payload_before_allocating() {
+        // Magic + a bogus chunk_count, with no directory bytes behind it.
+        let mut raw = Vec::new();
+        raw.extend_from_slice(CHUNK_MAGIC);
+        raw.write_u32::<LE>(0xFFFF_FFFF).unwrap();
+        assert_eq!(decompress_payload(&raw), None);
+    }
+
+    #[test]
+    fn rejects_a_compressed_span_whose_offset_and_size_would_overflow_usize() {
+        // One directory entry whose offset and size are both near u32::MAX,
+        // so offset + size overflows a 32-bit usize add; this must be
+        // rejected (as an out-of-bounds span) rather than panic or wrap.
+        let mut raw = Vec::new();
+        raw.extend_from_slice(CHUNK_MAGIC);
+        raw.write_u32::<LE>(1).unwrap();
+        raw.write_u32::<LE>(0).unwrap(); // codec: Zlib
+        raw.write_u32::<LE>(0xFFFF_FFF0).unwrap(); // compressed_offset
+        raw.write_u32::<LE>(0xFFFF_FFF0).unwrap(); // compressed_size
+        raw.write_u32::<LE>(4).unwrap(); // decompressed_size
+        assert_eq!(decompress_payload(&raw), None);
+    }
+}
@@ -0,0 +1,99 @@
+// Serializes `tree::SaveTree` to JSON, for diffing save files or piping
+// into external tooling.
+
+use crate::tree::{SaveTree, SaveTreeNode};
+use serde::Serialize;
+use wasm_bindgen::{JsCast, JsValue};
+
+#[derive(Serialize)]
+struct ExportNode {
+    name: String,
+    node_idx: usize,
+    own_bytes: u64,
+    total_bytes: u64,
+    children: Vec<ExportNode>,
+}
+
+impl From<&SaveTreeNode> for ExportNode {
+    fn from(node: &SaveTreeNode) -> Self {
+        ExportNode {
+            name: node.name.clone(),
+            node_idx: node.node_idx,
+            own_bytes: node.own_bytes,
+            total_bytes: node.total_bytes,
+            children: node.children.iter().map(ExportNode::from).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ExportTree {
+    roots: Vec<ExportNode>,
+}
+
+pub(crate) fn to_json(tree: &SaveTree) -> serde_json::Result<String> {
+    let export = ExportTree {
+        roots: tree.roots.iter().map(ExportNode::from).collect(),
+    };
+    serde_json::to_string_pretty(&export)
+}
+
+// Offers `json` to the user as a downloadable file via a Blob and a
+// temporary object URL.
+pub(crate) fn download_json(json: &str, filename: &str) -> Option<()> {
+    let mut bag = web_sys::BlobPropertyBag::new();
+    bag.type_("application/json");
+    let parts = js_sys::Array::new();
+    parts.push(&JsValue::from_str(json));
+    let blob = web_sys::Blob::new_with_str_sequence_and_options(&parts, &bag).ok()?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob).ok()?;
+
+    let document = web_sys::window()?.document()?;
+    let anchor = document
+        .create_element("a")
+        .ok()?
+        .dyn_into::<web_sys::HtmlAnchorElement>()
+        .ok()?;
+    anchor.set_href(&url);
+    anchor.set_download(filename);
+    anchor.click();
+    web_sys::Url::revoke_object_url(&url).ok()?;
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaf(node_idx: usize, name: &str, own_bytes: u64) -> SaveTreeNode {
+        SaveTreeNode {
+            node_idx,
+            name: name.to_string(),
+            own_bytes,
+            total_bytes: own_bytes,
+            children: vec![],
+        }
+    }
+
+    #[test]
+    fn to_json_emits_field_names_and_nested_children() {
+        let tree = SaveTree {
+            roots: vec![SaveTreeNode {
+                node_idx: 0,
+                name: "root".to_string(),
+                own_bytes: 10,
+                total_bytes: 30,
+                children: vec![leaf(1, "child", 20)],
+            }],
+        };
+        let json = to_json(&tree).expect("should serialize");
+        let parsed: serde_json::Value = serde_json::from_str(&json).expect("should parse");
+
+        assert_eq!(parsed["roots"][0]["name"], "root");
+        assert_eq!(parsed["roots"][0]["node_idx"], 0);
+        assert_eq!(parsed["roots"][0]["own_bytes"], 10);
+        assert_eq!(parsed["roots"][0]["total_bytes"], 30);
+        assert_eq!(parsed["roots"][0]["children"][0]["name"], "child");
+        assert_eq!(parsed["roots"][0]["children"][0]["total_bytes"], 20);
+    }
+}
@@ -0,0 +1,144 @@
+// Replaces the hardcoded ENOD/EDON magic check with a small registry:
+// classify a payload into a known `SaveFormat` by its footer magic, then
+// dispatch to the matching structural reader instead of a bare `None`.
+
+use crate::CPSave;
+
+// A known save layout. Each variant may use a different node-record
+// layout, packed-int width, or endianness; today only one is implemented.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SaveFormat {
+    CyberpunkV0,
+}
+
+// Why a payload couldn't be classified or read, surfaced to the UI
+// instead of a bare `None`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum FormatError {
+    UnknownMagic,
+    UnsupportedVersion {
+        found: u8,
+        supported: &'static [u8],
+    },
+    Malformed,
+}
+
+impl std::fmt::Display for FormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FormatError::UnknownMagic => write!(f, "unrecognized save format"),
+            FormatError::UnsupportedVersion { found, supported } => write!(
+                f,
+                "unsupported save version {} (supported: {:?})",
+                found, supported
+            ),
+            FormatError::Malformed => write!(f, "save file is malformed"),
+        }
+    }
+}
+
+// Everything the structural reader needs to parse one `SaveFormat`
+// variant: its magic bytes and, for variants that have one, the tree
+// versions it knows how to read.
+pub(crate) struct FormatDescriptor {
+    pub(crate) format: SaveFormat,
+    pub(crate) footer_magic: &'static [u8; 4],
+    pub(crate) tree_magic: &'static [u8; 4],
+    // The baseline CyberpunkV0 layout has no version byte at all --
+    // EDON is immediately followed by the node count -- so this is
+    // `false` for it. A version byte is only read, and validated
+    // against `supported_versions`, when this is `true`.
+    pub(crate) has_version_byte: bool,
+    pub(crate) supported_versions: &'static [u8],
+}
+
+const REGISTRY: &[FormatDescriptor] = &[FormatDescriptor {
+    format: SaveFormat::CyberpunkV0,
+    footer_magic: b"ENOD",
+    tree_magic: b"EDON",
+    has_version_byte: false,
+    supported_versions: &[],
+}];
+
+// Classifies a payload by its footer magic. The tree version (when the
+// descriptor has one) lives past the footer, so the structural reader
+// validates it once it has seeked to the tree header.
+fn detect(payload: &[u8]) -> Result<&'static FormatDescriptor, FormatError> {
+    use std::io::{Cursor, Read, Seek, SeekFrom};
+
+    if payload.len() < 8 {
+        return Err(FormatError::Malformed);
+    }
+    let mut input = Cursor::new(payload);
+    input
+        .seek(SeekFrom::End(-4))
+        .map_err(|_| FormatError::Malformed)?;
+    let mut sig_buf = [0u8; 4];
+    input
+        .read_exact(&mut sig_buf)
+        .map_err(|_| FormatError::Malformed)?;
+
+    REGISTRY
+        .iter()
+        .find(|d| d.footer_magic == &sig_buf)
+        .ok_or(FormatError::UnknownMagic)
+}
+
+// Detects a payload's `SaveFormat` and dispatches to the matching
+// structural reader.
+pub(crate) async fn read_save(payload: &[u8]) -> Result<CPSave, FormatError> {
+    let descriptor = detect(payload)?;
+    match descriptor.format {
+        SaveFormat::CyberpunkV0 => crate::read_cyberpunk_save(payload, descriptor).await,
+    }
+}
+
+// Checked by the structural reader only when `descriptor.has_version_byte`
+// is set; pulled out as its own function so the dispatch logic is
+// testable without needing a real save byte layout for every variant.
+pub(crate) fn validate_version(descriptor: &FormatDescriptor, version: u8) -> Result<(), FormatError> {
+    if descriptor.supported_versions.contains(&version) {
+        Ok(())
+    } else {
+        Err(FormatError::UnsupportedVersion {
+            found: version,
+            supported: descriptor.supported_versions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_footer_magic_is_reported_distinctly_from_a_bad_version() {
+        let mut payload = vec![0u8; 16];
+        payload[12..16].copy_from_slice(b"NOPE");
+        assert!(matches!(detect(&payload), Err(FormatError::UnknownMagic)));
+    }
+
+    #[test]
+    fn recognizes_the_cyberpunk_v0_footer_magic() {
+        let mut payload = vec![0u8; 16];
+        payload[12..16].copy_from_slice(b"ENOD");
+        let descriptor = detect(&payload).expect("should classify");
+        assert_eq!(descriptor.format, SaveFormat::CyberpunkV0);
+    }
+
+    #[test]
+    fn validate_version_rejects_anything_outside_the_supported_list() {
+        let descriptor = FormatDescriptor {
+            format: SaveFormat::CyberpunkV0,
+            footer_magic: b"ENOD",
+            tree_magic: b"EDON",
+            has_version_byte: true,
+            supported_versions: &[1, 2],
+        };
+        assert_eq!(validate_version(&descriptor, 1), Ok(()));
+        assert_eq!(
+            validate_version(&descriptor, 9),
+            Err(FormatError::UnsupportedVersion { found: 9, supported: &[1, 2] })
+        );
+    }
+}
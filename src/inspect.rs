@@ -0,0 +1,171 @@
+// Slices a node's raw bytes and opportunistically recognizes packed
+// integers and length-prefixed strings within them, reusing the same
+// `ReadCDPRExt` decoders the tree reader uses.
+
+use crate::ReadCDPRExt;
+use std::io::Cursor;
+
+/// Hex-view rendering is capped to this many bytes per node to keep the
+/// app responsive on oversized blobs (e.g. a bulk inventory array).
+pub(crate) const MAX_INSPECT_BYTES: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FieldKind {
+    PackedInt,
+    PString,
+    Unknown,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct HexField {
+    pub(crate) offset: usize,
+    pub(crate) len: usize,
+    pub(crate) kind: FieldKind,
+    pub(crate) label: String,
+}
+
+/// A node's raw bytes (capped to [`MAX_INSPECT_BYTES`]) plus whatever
+/// packed-int/string fields were recognized within them.
+pub(crate) struct NodeInspection<'a> {
+    pub(crate) bytes: &'a [u8],
+    pub(crate) fields: Vec<HexField>,
+    pub(crate) truncated: bool,
+}
+
+impl<'a> NodeInspection<'a> {
+    /// The field a given byte offset falls into, for highlighting a hex
+    /// dump. Every byte belongs to exactly one field, since unrecognized
+    /// spans are themselves recorded as `FieldKind::Unknown` runs.
+    pub(crate) fn kind_at(&self, idx: usize) -> FieldKind {
+        self.fields
+            .iter()
+            .find(|f| idx >= f.offset && idx < f.offset + f.len)
+            .map(|f| f.kind)
+            .unwrap_or(FieldKind::Unknown)
+    }
+}
+
+/// Slices `payload[data_offset .. data_offset + data_size]` and scans it
+/// left to right, greedily recognizing length-prefixed strings and bare
+/// packed integers at each position and falling back to one-byte-at-a-time
+/// `Unknown` runs where nothing decodes plausibly.
+pub(crate) fn inspect_node(payload: &[u8], data_offset: u32, data_size: u32) -> NodeInspection<'_> {
+    let start = (data_offset as usize).min(payload.len());
+    let end = start.saturating_add(data_size as usize).min(payload.len());
+    let full = &payload[start..end];
+    let truncated = full.len() > MAX_INSPECT_BYTES;
+    let bytes = &full[..full.len().min(MAX_INSPECT_BYTES)];
+
+    let mut fields = vec![];
+    let mut pos = 0usize;
+    let mut unknown_run_start: Option<usize> = None;
+    while pos < bytes.len() {
+        if let Some((field_len, kind, label)) = try_decode_field(&bytes[pos..]) {
+            if let Some(run_start) = unknown_run_start.take() {
+                fields.push(unknown_field(run_start, pos - run_start));
+            }
+            fields.push(HexField { offset: pos, len: field_len, kind, label });
+            pos += field_len;
+        } else {
+            if unknown_run_start.is_none() {
+                unknown_run_start = Some(pos);
+            }
+            pos += 1;
+        }
+    }
+    if let Some(run_start) = unknown_run_start {
+        fields.push(unknown_field(run_start, bytes.len() - run_start));
+    }
+
+    NodeInspection { bytes, fields, truncated }
+}
+
+fn unknown_field(offset: usize, len: usize) -> HexField {
+    HexField {
+        offset,
+        len,
+        kind: FieldKind::Unknown,
+        label: "unknown".to_string(),
+    }
+}
+
+/// Tries the more specific length-prefixed string shape first, falling
+/// back to a bare packed int.
+fn try_decode_field(slice: &[u8]) -> Option<(usize, FieldKind, String)> {
+    if let Some((len, s)) = try_decode_pstr(slice) {
+        return Some((len, FieldKind::PString, format!("{:?}", s)));
+    }
+    try_decode_packed_int(slice)
+}
+
+fn try_decode_pstr(slice: &[u8]) -> Option<(usize, String)> {
+    let mut cursor = Cursor::new(slice);
+    let s = cursor.read_pstr().ok()?;
+    let len = cursor.position() as usize;
+    if len <= 1 || len > slice.len() || s.is_empty() {
+        return None;
+    }
+    // A length prefix can decode to a plausible count purely by chance;
+    // only trust it once the string it bounds looks like real text.
+    if !s.chars().all(is_plausible_string_char) {
+        return None;
+    }
+    Some((len, s))
+}
+
+fn try_decode_packed_int(slice: &[u8]) -> Option<(usize, FieldKind, String)> {
+    let mut cursor = Cursor::new(slice);
+    let val = cursor.read_packed_int().ok()?;
+    let len = cursor.position() as usize;
+    if len == 0 || len > slice.len() {
+        return None;
+    }
+    Some((len, FieldKind::PackedInt, val.to_string()))
+}
+
+fn is_plausible_string_char(c: char) -> bool {
+    c.is_ascii_graphic() || c == ' ' || (!c.is_ascii() && !c.is_control())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefers_a_plausible_pstr_over_treating_its_prefix_as_a_bare_int() {
+        // 0x83 encodes a UTF-8 pstr of length 3: "abc".
+        let slice = [0x83, b'a', b'b', b'c', 0x00];
+        let (len, kind, label) = try_decode_field(&slice).expect("should decode a field");
+        assert_eq!(kind, FieldKind::PString);
+        assert_eq!(len, 4);
+        assert_eq!(label, "\"abc\"");
+    }
+
+    #[test]
+    fn falls_back_to_a_bare_packed_int_when_the_bytes_dont_look_like_text() {
+        // 0x83 would be a length-3 pstr, but the bytes that follow aren't
+        // plausible text, so this should be read as a bare packed int
+        // instead (consuming only the one byte).
+        let slice = [0x83, 0x00, 0x01, 0x02];
+        let (len, kind, _) = try_decode_field(&slice).expect("should decode a field");
+        assert_eq!(kind, FieldKind::PackedInt);
+        assert_eq!(len, 1);
+    }
+
+    #[test]
+    fn inspect_node_covers_every_byte_with_exactly_one_field() {
+        let payload = vec![0x83, b'a', b'b', b'c', 0xFF, 0xFF, 0xFF];
+        let inspection = inspect_node(&payload, 0, payload.len() as u32);
+        let covered: usize = inspection.fields.iter().map(|f| f.len).sum();
+        assert_eq!(covered, payload.len());
+        assert_eq!(inspection.kind_at(0), FieldKind::PString);
+        assert_eq!(inspection.kind_at(4), FieldKind::Unknown);
+    }
+
+    #[test]
+    fn inspect_node_clamps_an_out_of_bounds_span_instead_of_panicking() {
+        let payload = vec![1, 2, 3];
+        let inspection = inspect_node(&payload, 2, 100);
+        assert_eq!(inspection.bytes, &[3]);
+    }
+}
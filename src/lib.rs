@@ -6,14 +6,28 @@ use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use yew::prelude::*;
 
+mod decompress;
+mod export;
+mod format;
+mod inspect;
+mod tree;
+
 struct Model {
     link: ComponentLink<Self>,
-    info_ref: NodeRef,
+    save: Option<CPSave>,
+    tree: Option<tree::SaveTree>,
+    status: Option<String>,
+    expanded: std::collections::HashSet<usize>,
+    selected: Option<usize>,
 }
 
 enum Msg {
     AllowDrop(DragEvent),
     DoDrop(DragEvent),
+    Loaded(CPSave),
+    LoadFailed(String),
+    NodeClicked(usize),
+    ExportJson,
 }
 
 trait ReadCDPRExt {
@@ -62,51 +76,66 @@ impl<T> ReadCDPRExt for T where T : Read {
 }
 
 #[derive(Debug)]
-struct CPSave {
-    payload: Vec<u8>,
-    nodes: Vec<CPNode>,
+pub(crate) struct CPSave {
+    pub(crate) payload: Vec<u8>,
+    pub(crate) nodes: Vec<CPNode>,
 }
 
 #[derive(Debug)]
-struct CPNode {
-    name: String,
-    next_idx: i32,
-    child_idx: i32,
-    data_offset: u32,
-    data_size: u32,
+pub(crate) struct CPNode {
+    pub(crate) name: String,
+    pub(crate) next_idx: i32,
+    pub(crate) child_idx: i32,
+    pub(crate) data_offset: u32,
+    pub(crate) data_size: u32,
 }
 
-async fn read_save_structure(payload: &[u8]) -> Option<CPSave> {
+// Structural reader for the CyberpunkV0 layout, reached only through
+// `format::read_save`, which has already matched the footer magic; the
+// tree magic (and version byte, for descriptors that have one) is still
+// validated here since it lives past the footer.
+async fn read_cyberpunk_save(
+    payload: &[u8],
+    descriptor: &format::FormatDescriptor,
+) -> Result<CPSave, format::FormatError> {
+    use format::FormatError;
+
     let mut input = Cursor::new(&payload);
-    input.seek(SeekFrom::End(-8)).ok()?;
-    let tree_offset = input.read_u32::<LE>().ok()?;
+    input.seek(SeekFrom::End(-8)).map_err(|_| FormatError::Malformed)?;
+    let tree_offset = input.read_u32::<LE>().map_err(|_| FormatError::Malformed)?;
     let mut sig_buf = [0u8; 4];
-    input.read_exact(&mut sig_buf).ok()?;
-    if &sig_buf != b"ENOD" {
-        return None;
+    input.read_exact(&mut sig_buf).map_err(|_| FormatError::Malformed)?;
+    if &sig_buf != descriptor.footer_magic {
+        return Err(FormatError::UnknownMagic);
     }
 
-    input.seek(SeekFrom::Start(tree_offset as u64)).ok()?;
+    input.seek(SeekFrom::Start(tree_offset as u64)).map_err(|_| FormatError::Malformed)?;
     info!("tree offset: {}", tree_offset);
-    input.read_exact(&mut sig_buf).ok()?;
-    if &sig_buf != b"EDON" {
-        return None;
+    input.read_exact(&mut sig_buf).map_err(|_| FormatError::Malformed)?;
+    if &sig_buf != descriptor.tree_magic {
+        return Err(FormatError::Malformed);
+    }
+    if descriptor.has_version_byte {
+        let version = input.read_u8().map_err(|_| FormatError::Malformed)?;
+        format::validate_version(descriptor, version)?;
+        info!("save version: {}", version);
     }
-    let node_count = input.read_packed_int().ok()?;
+
+    let node_count = input.read_packed_int().map_err(|_| FormatError::Malformed)?;
     info!("node count: {}", node_count);
 
     let mut nodes = vec![];
     for _ in 0..node_count {
-        let name = input.read_pstr().ok()?;
-        let next_idx = input.read_i32::<LE>().ok()?;
-        let child_idx = input.read_i32::<LE>().ok()?;
-        let data_offset = input.read_u32::<LE>().ok()?;
-        let data_size = input.read_u32::<LE>().ok()?;
+        let name = input.read_pstr().map_err(|_| FormatError::Malformed)?;
+        let next_idx = input.read_i32::<LE>().map_err(|_| FormatError::Malformed)?;
+        let child_idx = input.read_i32::<LE>().map_err(|_| FormatError::Malformed)?;
+        let data_offset = input.read_u32::<LE>().map_err(|_| FormatError::Malformed)?;
+        let data_size = input.read_u32::<LE>().map_err(|_| FormatError::Malformed)?;
         info!("{:?}", (&name, next_idx, child_idx, data_offset, data_size));
         let node = CPNode { name, next_idx, child_idx, data_offset, data_size };
         nodes.push(node);
     }
-    Some(CPSave {
+    Ok(CPSave {
         payload: payload.into(),
         nodes,
     })
@@ -118,7 +147,11 @@ impl Component for Model {
     fn create(_: Self::Properties, link: ComponentLink<Self>) -> Self {
         Self {
             link,
-            info_ref: NodeRef::default(),
+            save: None,
+            tree: None,
+            status: None,
+            expanded: std::collections::HashSet::new(),
+            selected: None,
         }
     }
 
@@ -134,48 +167,63 @@ impl Component for Model {
                 for i in 0..files.length() {
                     let file = files.item(i).unwrap();
                     info!("{}: {:?}", i, file);
-                    let size = file.size() as i64;
                     let buf_future = wasm_bindgen_futures::JsFuture::from(file.array_buffer());
-                    let info_ref = self.info_ref.clone();
+                    let link = self.link.clone();
                     wasm_bindgen_futures::spawn_local(async move {
                         match buf_future.await {
                             Ok(buf) => {
                                 let typebuf = js_sys::Uint8Array::new(&buf);
-                                let payload = typebuf.to_vec();
-                                if let Some(save) = read_save_structure(&payload).await {
-                                    let mut txt = String::new();
-                                    let mut child_bytes = std::collections::HashMap::new();
-                                    for (idx, node) in save.nodes.iter().enumerate() {
-                                        let mut child_idx = node.child_idx;
-                                        let mut child_sum = 0;
-                                        while child_idx != -1 {
-                                            let child = &save.nodes[child_idx as usize];
-                                            child_sum += child.data_size;
-                                            child_idx = child.next_idx;
-                                        }
-                                        child_bytes.insert(idx, child_sum);
-                                    }
-
-                                    for (idx, node) in save.nodes.iter().enumerate() {
-                                        let total_bytes = node.data_size;
-                                        let child_sum = child_bytes.get(&idx).unwrap();
-                                        let own_bytes = total_bytes - child_sum;
-                                        txt.push_str(&format!("{}: {} own bytes, {} total bytes\n", &node.name, own_bytes, total_bytes));
+                                let raw = typebuf.to_vec();
+                                match decompress::decompress_payload(&raw) {
+                                    Some(payload) => match format::read_save(&payload).await {
+                                        Ok(save) => link.send_message(Msg::Loaded(save)),
+                                        Err(e) => link.send_message(Msg::LoadFailed(e.to_string())),
+                                    },
+                                    None => {
+                                        warn!("could not decompress payload");
+                                        link.send_message(Msg::LoadFailed("Could not decompress save".into()));
                                     }
-                                    info_ref.cast::<web_sys::HtmlPreElement>().unwrap().set_inner_text(&txt);
-                                }
-                                else {
-                                    info_ref.cast::<web_sys::HtmlPreElement>().unwrap().set_inner_text("Could not load save");
                                 }
                             }
                             _ => {
                                 warn!("Could not read file");
-                                info_ref.cast::<web_sys::HtmlPreElement>().unwrap().set_inner_text("Could not read file");
+                                link.send_message(Msg::LoadFailed("Could not read file".into()));
                             }
                         }
                     });
                 }
             }
+            Msg::Loaded(save) => {
+                self.tree = Some(tree::build_tree(&save.nodes));
+                self.save = Some(save);
+                self.status = None;
+                self.expanded.clear();
+                self.selected = None;
+            }
+            Msg::LoadFailed(reason) => {
+                self.save = None;
+                self.tree = None;
+                self.status = Some(reason);
+                self.selected = None;
+            }
+            Msg::NodeClicked(idx) => {
+                self.selected = Some(idx);
+                if !self.expanded.remove(&idx) {
+                    self.expanded.insert(idx);
+                }
+            }
+            Msg::ExportJson => {
+                if let Some(tree) = &self.tree {
+                    match export::to_json(tree) {
+                        Ok(json) => {
+                            if export::download_json(&json, "save-tree.json").is_none() {
+                                warn!("failed to trigger JSON download");
+                            }
+                        }
+                        Err(e) => warn!("failed to serialize save tree: {}", e),
+                    }
+                }
+            }
         }
         true
     }
@@ -196,12 +244,150 @@ impl Component for Model {
             >
                 {"[drag a sav.dat file onto this header]"}
             </h1>
-            <pre ref=self.info_ref.clone()></pre>
+            { self.view_status() }
+            { self.view_tree() }
+            { self.view_inspector() }
             </>
         }
     }
 }
 
+impl Model {
+    fn view_status(&self) -> Html {
+        match &self.status {
+            Some(msg) => html! { <pre>{ msg }</pre> },
+            None => html! {},
+        }
+    }
+
+    fn view_tree(&self) -> Html {
+        match &self.tree {
+            Some(save_tree) if !save_tree.roots.is_empty() => {
+                let max_bytes = save_tree.roots.iter().map(|n| n.total_bytes).max().unwrap_or(1).max(1);
+                html! {
+                    <>
+                    <button onclick=self.link.callback(|_| Msg::ExportJson),>{ "export as JSON" }</button>
+                    <ul class="save-tree">
+                        { for save_tree.roots.iter().map(|node| self.view_node(node, max_bytes)) }
+                    </ul>
+                    </>
+                }
+            }
+            _ => html! {},
+        }
+    }
+
+    fn view_node(&self, node: &tree::SaveTreeNode, max_bytes: u64) -> Html {
+        let has_children = !node.children.is_empty();
+        let expanded = has_children && self.expanded.contains(&node.node_idx);
+        let toggle_label = if !has_children {
+            " "
+        } else if expanded {
+            "\u{25be}"
+        } else {
+            "\u{25b8}"
+        };
+        let idx = node.node_idx;
+        let is_selected = self.selected == Some(idx);
+        let bar_pct = (node.total_bytes as f64 / max_bytes as f64 * 100.0).min(100.0);
+        let row_class = if is_selected { "save-tree-row save-tree-row-selected" } else { "save-tree-row" };
+        html! {
+            <li>
+                <div
+                    class=row_class,
+                    onclick=self.link.callback(move |_| Msg::NodeClicked(idx)),
+                >
+                    <span class="save-tree-toggle">{ toggle_label }</span>
+                    <span class="save-tree-name">{ &node.name }</span>
+                    <span class="save-tree-bytes">
+                        { format!("{} own / {} total", node.own_bytes, node.total_bytes) }
+                    </span>
+                    <span class="save-tree-bar", style=format!("width: {:.1}%", bar_pct),></span>
+                </div>
+                { if expanded {
+                    html! {
+                        <ul>
+                            { for node.children.iter().map(|child| self.view_node(child, max_bytes)) }
+                        </ul>
+                    }
+                } else {
+                    html! {}
+                }}
+            </li>
+        }
+    }
+
+    fn view_inspector(&self) -> Html {
+        let (save, idx) = match (&self.save, self.selected) {
+            (Some(save), Some(idx)) if idx < save.nodes.len() => (save, idx),
+            _ => return html! {},
+        };
+        let node = &save.nodes[idx];
+        let inspection = inspect::inspect_node(&save.payload, node.data_offset, node.data_size);
+
+        let rows = inspection.bytes.chunks(16).enumerate().map(|(row_idx, row)| {
+            let row_offset = row_idx * 16;
+            html! {
+                <tr>
+                    <td class="hex-offset">{ format!("{:08x}", row_offset) }</td>
+                    <td class="hex-bytes">
+                        { for row.iter().enumerate().map(|(i, b)| {
+                            let class = hex_byte_class(inspection.kind_at(row_offset + i));
+                            html! { <span class=class>{ format!("{:02x} ", b) }</span> }
+                        }) }
+                    </td>
+                    <td class="hex-ascii">
+                        { for row.iter().map(|&b| {
+                            let c = if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' };
+                            html! { <span>{ c }</span> }
+                        }) }
+                    </td>
+                </tr>
+            }
+        });
+
+        let field_rows = inspection
+            .fields
+            .iter()
+            .filter(|f| f.kind != inspect::FieldKind::Unknown)
+            .map(|f| {
+                html! {
+                    <tr>
+                        <td>{ format!("{:08x}", f.offset) }</td>
+                        <td>{ format!("{:?}", f.kind) }</td>
+                        <td>{ &f.label }</td>
+                    </tr>
+                }
+            });
+
+        html! {
+            <div class="node-inspector">
+                <h2>{ format!("{} ({} bytes)", node.name, node.data_size) }</h2>
+                { if inspection.truncated {
+                    html! { <p class="truncation-note">{ format!("showing first {} bytes", inspect::MAX_INSPECT_BYTES) }</p> }
+                } else {
+                    html! {}
+                }}
+                <table class="hex-view">
+                    <tbody>{ for rows }</tbody>
+                </table>
+                <h3>{ "recognized fields" }</h3>
+                <table class="field-list">
+                    <tbody>{ for field_rows }</tbody>
+                </table>
+            </div>
+        }
+    }
+}
+
+fn hex_byte_class(kind: inspect::FieldKind) -> &'static str {
+    match kind {
+        inspect::FieldKind::PackedInt => "hex-byte hex-byte-int",
+        inspect::FieldKind::PString => "hex-byte hex-byte-str",
+        inspect::FieldKind::Unknown => "hex-byte",
+    }
+}
+
 #[wasm_bindgen(start)]
 pub fn run_app() {
     wasm_logger::init(wasm_logger::Config::default());
@@ -0,0 +1,193 @@
+// Reconstructs the real parent-child tree from `CPNode`'s flat
+// next_idx/child_idx sibling-list encoding, aggregating byte sizes
+// bottom-up, `du`-style.
+
+use crate::CPNode;
+use std::collections::HashMap;
+
+/// One node of the reconstructed hierarchy.
+#[derive(Debug, Clone)]
+pub struct SaveTreeNode {
+    pub node_idx: usize,
+    pub name: String,
+    pub own_bytes: u64,
+    pub total_bytes: u64,
+    pub children: Vec<SaveTreeNode>,
+}
+
+/// Rooted at whichever node no other node lists as a child.
+#[derive(Debug, Clone)]
+pub struct SaveTree {
+    pub roots: Vec<SaveTreeNode>,
+}
+
+pub fn build_tree(nodes: &[CPNode]) -> SaveTree {
+    let mut on_stack = vec![false; nodes.len()];
+    let mut memo = HashMap::new();
+    let roots = find_roots(nodes)
+        .into_iter()
+        .map(|idx| build_node(nodes, idx, &mut on_stack, &mut memo))
+        .collect();
+    SaveTree { roots }
+}
+
+fn find_roots(nodes: &[CPNode]) -> Vec<usize> {
+    if nodes.is_empty() {
+        return vec![];
+    }
+    let mut is_child = vec![false; nodes.len()];
+    for node in nodes {
+        let mut idx = node.child_idx;
+        while idx != -1 {
+            match is_child.get_mut(idx as usize) {
+                Some(slot) => *slot = true,
+                None => break,
+            }
+            idx = nodes[idx as usize].next_idx;
+        }
+    }
+    let roots: Vec<usize> = is_child
+        .iter()
+        .enumerate()
+        .filter(|(_, &is_child)| !is_child)
+        .map(|(idx, _)| idx)
+        .collect();
+    if roots.is_empty() {
+        // Every node claims a parent, which only happens via a cycle;
+        // fall back to node 0 so something still renders.
+        vec![0]
+    } else {
+        roots
+    }
+}
+
+// `on_stack` tracks only the current recursion path, not every node
+// visited overall, so a back-edge to one of its own ancestors is the only
+// thing that trips the cycle guard below -- a node legitimately shared by
+// two parents (a DAG) is not a cycle. `memo` caches each node's completed
+// subtree the first time it's built (once it's off the recursion stack),
+// so a diamond of shared nodes is built once per node instead of once per
+// path to it, which is exponential in the diamond depth.
+fn build_node(
+    nodes: &[CPNode],
+    idx: usize,
+    on_stack: &mut [bool],
+    memo: &mut HashMap<usize, SaveTreeNode>,
+) -> SaveTreeNode {
+    let node = &nodes[idx];
+    if on_stack[idx] {
+        return SaveTreeNode {
+            node_idx: idx,
+            name: format!("{} (cycle)", node.name),
+            own_bytes: node.data_size as u64,
+            total_bytes: node.data_size as u64,
+            children: vec![],
+        };
+    }
+    if let Some(cached) = memo.get(&idx) {
+        return cached.clone();
+    }
+    on_stack[idx] = true;
+
+    let mut children = vec![];
+    let mut child_idx = node.child_idx;
+    while child_idx != -1 {
+        let idx = child_idx as usize;
+        if idx >= nodes.len() {
+            break;
+        }
+        children.push(build_node(nodes, idx, on_stack, memo));
+        child_idx = nodes[idx].next_idx;
+    }
+
+    on_stack[idx] = false;
+
+    let child_total: u64 = children.iter().map(|c| c.total_bytes).sum();
+    let own_bytes = (node.data_size as u64).saturating_sub(child_total);
+    let total_bytes = own_bytes + child_total;
+
+    let built = SaveTreeNode {
+        node_idx: idx,
+        name: node.name.clone(),
+        own_bytes,
+        total_bytes,
+        children,
+    };
+    memo.insert(idx, built.clone());
+    built
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str, next_idx: i32, child_idx: i32, data_size: u32) -> CPNode {
+        CPNode {
+            name: name.to_string(),
+            next_idx,
+            child_idx,
+            data_offset: 0,
+            data_size,
+        }
+    }
+
+    #[test]
+    fn aggregates_own_and_total_bytes_over_a_linear_chain() {
+        // root -> child, root.data_size covers both.
+        let nodes = vec![node("root", -1, 1, 100), node("child", -1, -1, 40)];
+        let tree = build_tree(&nodes);
+        assert_eq!(tree.roots.len(), 1);
+        let root = &tree.roots[0];
+        assert_eq!(root.total_bytes, 100);
+        assert_eq!(root.own_bytes, 60);
+        assert_eq!(root.children[0].own_bytes, 40);
+        assert_eq!(root.children[0].total_bytes, 40);
+    }
+
+    #[test]
+    fn guards_against_a_cycle_instead_of_recursing_forever() {
+        // node 0's only child is node 1, whose only child is node 0.
+        let nodes = vec![node("a", -1, 1, 10), node("b", -1, 0, 10)];
+        let tree = build_tree(&nodes);
+        assert_eq!(tree.roots.len(), 1);
+        let a = &tree.roots[0];
+        assert_eq!(a.children[0].children[0].name, "a (cycle)");
+    }
+
+    #[test]
+    fn a_node_shared_by_two_parents_is_not_mislabeled_a_cycle() {
+        // Both "left" and "right" list "shared" as their child -- a DAG,
+        // not a cycle, since neither is an ancestor of itself.
+        let nodes = vec![
+            node("left", 1, 2, 50),
+            node("right", -1, 2, 50),
+            node("shared", -1, -1, 20),
+        ];
+        let tree = build_tree(&nodes);
+        assert_eq!(tree.roots.len(), 2);
+        for root in &tree.roots {
+            assert_eq!(root.children[0].name, "shared");
+        }
+    }
+
+    #[test]
+    fn a_deep_chain_of_diamonds_builds_without_exponential_blowup() {
+        // A single root's two children are level 0's pair; each level's
+        // pair both list the next level's pair as their children, so the
+        // number of root-to-leaf paths doubles per level. Without
+        // memoizing completed subtrees, building this would revisit the
+        // bottom level once per path -- 2^LEVELS rebuilds. 20 levels is
+        // already far past what an unmemoized rebuild could finish here.
+        const LEVELS: i32 = 20;
+        let mut nodes = vec![node("root", -1, 1, 1)];
+        for level in 0..LEVELS {
+            let base = 1 + level * 2;
+            let child_idx = if level + 1 < LEVELS { base + 2 } else { -1 };
+            nodes.push(node(&format!("L{}a", level), base + 1, child_idx, 1));
+            nodes.push(node(&format!("L{}b", level), -1, child_idx, 1));
+        }
+        let tree = build_tree(&nodes);
+        assert_eq!(tree.roots.len(), 1);
+        assert_eq!(tree.roots[0].total_bytes, 1u64 << LEVELS);
+    }
+}